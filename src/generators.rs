@@ -19,27 +19,70 @@
 #![allow(non_snake_case)]
 #![deny(missing_docs)]
 
-// XXX we should use Sha3 everywhere
+use std::sync::Arc;
 
+use byteorder::{ByteOrder, LittleEndian};
 use curve25519_dalek::ristretto;
 use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::scalar::Scalar;
-use sha2::{Digest, Sha512};
+use curve25519_dalek::traits::Identity;
+use tiny_keccak::Keccak;
+
+/// Window size (in bits) used by the radix-16 precomputed tables in
+/// [`GeneratorsPrecomp`]: each table stores the nonzero multiples
+/// `1*P, .., 15*P` of a generator `P`.
+const PRECOMP_WINDOW_BITS: usize = 4;
+/// Number of nonzero multiples stored per generator (`2^4 - 1`).
+const PRECOMP_TABLE_SIZE: usize = (1 << PRECOMP_WINDOW_BITS) - 1;
+/// Number of 4-bit digits in a 256-bit scalar.
+const PRECOMP_SCALAR_DIGITS: usize = 256 / PRECOMP_WINDOW_BITS;
 
 /// The `GeneratorsChain` creates an arbitrary-long sequence of orthogonal generators.
-/// The sequence can be deterministically produced starting with an arbitrary point.
+///
+/// Generator `i` is the `i`-th 64-byte block squeezed from a SHAKE256 XOF
+/// seeded with a domain separator and `label`, mapped to a point with
+/// `RistrettoPoint::from_uniform_bytes`. Squeezing a sponge is inherently
+/// serial, so `get(i)` reads and discards the `i` blocks before it
+/// (`O(i)`) rather than jumping to it directly; `B`/`B_blinding` stay
+/// reproducible for the same `label` regardless of that.
 struct GeneratorsChain {
-    next_point: RistrettoPoint,
+    label: Vec<u8>,
+    reader: tiny_keccak::XofReader,
 }
 
 impl GeneratorsChain {
     /// Creates a chain of generators, determined by the hash of `label`.
     fn new(label: &[u8]) -> Self {
-        let mut hash = Sha512::default();
-        hash.input(b"GeneratorsChainInit");
-        hash.input(label);
-        let next_point = RistrettoPoint::from_hash(hash);
-        GeneratorsChain { next_point }
+        GeneratorsChain {
+            label: label.to_vec(),
+            reader: Self::xof_reader(label),
+        }
+    }
+
+    /// Seeds a fresh SHAKE256 XOF with the domain separator and `label`.
+    fn xof_reader(label: &[u8]) -> tiny_keccak::XofReader {
+        let mut shake = Keccak::new_shake256();
+        shake.update(b"GeneratorsChain");
+        shake.update(label);
+        shake.xof()
+    }
+
+    /// Advances `reader` past `n` 64-byte blocks without emitting them.
+    fn fast_forward(reader: &mut tiny_keccak::XofReader, n: usize) {
+        let mut block = [0u8; 64];
+        for _ in 0..n {
+            reader.squeeze(&mut block);
+        }
+    }
+
+    /// Returns the `i`-th generator, reading and discarding the `i` blocks
+    /// before it in the stream (`O(i)`).
+    fn get(&self, i: usize) -> RistrettoPoint {
+        let mut reader = Self::xof_reader(&self.label);
+        Self::fast_forward(&mut reader, i);
+        let mut block = [0u8; 64];
+        reader.squeeze(&mut block);
+        RistrettoPoint::from_uniform_bytes(&block)
     }
 }
 
@@ -52,12 +95,9 @@ impl Default for GeneratorsChain {
 impl Iterator for GeneratorsChain {
     type Item = RistrettoPoint;
     fn next(&mut self) -> Option<Self::Item> {
-        let current_point = self.next_point;
-        let mut hash = Sha512::default();
-        hash.input(b"GeneratorsChainNext");
-        hash.input(current_point.compress().as_bytes());
-        self.next_point = RistrettoPoint::from_hash(hash);
-        Some(current_point)
+        let mut block = [0u8; 64];
+        self.reader.squeeze(&mut block);
+        Some(RistrettoPoint::from_uniform_bytes(&block))
     }
 }
 
@@ -74,6 +114,71 @@ pub struct Generators {
     G: Vec<RistrettoPoint>,
     /// Per-bit generators for the bit blinding factors
     H: Vec<RistrettoPoint>,
+    /// Precomputed multiplication tables for `G` and `H`, built on demand
+    /// by [`Generators::precompute`].
+    precomp: Option<Arc<GeneratorsPrecomp>>,
+}
+
+/// Precomputed radix-16 multiplication tables for a fixed set of `G`/`H`
+/// generators, used by [`GeneratorsView::multiscalar_mul_precomputed`].
+/// Built once and shared behind an `Arc` since each table set is several
+/// megabytes. Leaves out the Pedersen bases `B`/`B_blinding`, since callers
+/// may swap those independently; combine a precomputed result with a plain
+/// `ristretto::multiscalar_mul` against `B`/`B_blinding` instead.
+struct GeneratorsPrecomp {
+    /// One radix-16 table per entry of `G`, holding its multiples `1..=15`.
+    G_tables: Vec<[RistrettoPoint; PRECOMP_TABLE_SIZE]>,
+    /// One radix-16 table per entry of `H`, holding its multiples `1..=15`.
+    H_tables: Vec<[RistrettoPoint; PRECOMP_TABLE_SIZE]>,
+}
+
+impl GeneratorsPrecomp {
+    /// Builds the table of multiples `1*point, .., 15*point`.
+    fn build_table(point: RistrettoPoint) -> [RistrettoPoint; PRECOMP_TABLE_SIZE] {
+        let mut table = [point; PRECOMP_TABLE_SIZE];
+        let mut running = point;
+        for entry in table.iter_mut().skip(1) {
+            running = running + point;
+            *entry = running;
+        }
+        table
+    }
+
+    /// Splits a scalar into its 4-bit digits, most-significant first.
+    fn digits(scalar: &Scalar) -> [u8; PRECOMP_SCALAR_DIGITS] {
+        let mut digits = [0u8; PRECOMP_SCALAR_DIGITS];
+        for (i, &byte) in scalar.as_bytes().iter().enumerate() {
+            digits[2 * i] = byte & 0x0f;
+            digits[2 * i + 1] = byte >> 4;
+        }
+        digits.reverse();
+        digits
+    }
+
+    /// Computes `sum_i scalars[i] * tables[i]` by processing one radix-16
+    /// digit of every scalar per iteration, skipping the table lookup for
+    /// zero digits.
+    fn radix16_multiscalar_mul(
+        scalars: &[Scalar],
+        tables: &[&[RistrettoPoint; PRECOMP_TABLE_SIZE]],
+    ) -> RistrettoPoint {
+        assert_eq!(scalars.len(), tables.len());
+        let digits: Vec<_> = scalars.iter().map(Self::digits).collect();
+
+        let mut sum = RistrettoPoint::identity();
+        for digit_index in 0..PRECOMP_SCALAR_DIGITS {
+            for _ in 0..PRECOMP_WINDOW_BITS {
+                sum = sum + sum;
+            }
+            for (table, digit_list) in tables.iter().zip(digits.iter()) {
+                let digit = digit_list[digit_index];
+                if digit != 0 {
+                    sum = sum + table[(digit - 1) as usize];
+                }
+            }
+        }
+        sum
+    }
 }
 
 /// Represents a view into `Generators` relevant to a specific range proof.
@@ -84,6 +189,120 @@ pub struct GeneratorsView<'a> {
     pub G: &'a [RistrettoPoint],
     /// Per-bit generators for the bit blinding factors
     pub H: &'a [RistrettoPoint],
+    /// Precomputed tables for `G` and `H`, present once `Generators::precompute`
+    /// has been called on the `Generators` this view was produced from.
+    precomp: Option<&'a GeneratorsPrecomp>,
+    /// Offset of this view's slice within the full `G`/`H` vectors, so that
+    /// `precomp`'s tables (which cover the full vectors) can be re-sliced
+    /// to line up with `G`/`H`.
+    precomp_range: ::std::ops::Range<usize>,
+}
+
+impl<'a> GeneratorsView<'a> {
+    /// Computes `<a, G> + <b, H>` using the tables built by
+    /// [`Generators::precompute`], instead of a plain multiscalar multiplication.
+    ///
+    /// # Panics
+    /// Panics if `Generators::precompute` was not called on the
+    /// `Generators` this view was produced from, or if `a`/`b` don't match
+    /// the number of generators in this view.
+    pub fn multiscalar_mul_precomputed(&self, a: &[Scalar], b: &[Scalar]) -> RistrettoPoint {
+        let precomp = self
+            .precomp
+            .expect("Generators::precompute() was not called");
+        assert_eq!(a.len(), self.G.len());
+        assert_eq!(b.len(), self.H.len());
+
+        let scalars: Vec<Scalar> = a.iter().cloned().chain(b.iter().cloned()).collect();
+        let tables: Vec<_> = precomp.G_tables[self.precomp_range.clone()]
+            .iter()
+            .chain(precomp.H_tables[self.precomp_range.clone()].iter())
+            .collect();
+        GeneratorsPrecomp::radix16_multiscalar_mul(&scalars, &tables)
+    }
+
+    /// Creates a vector Pedersen commitment to `a` against `G` and `b`
+    /// against `H`, blinded along `pedersen_generators.B_blinding`:
+    ///
+    /// ```text
+    /// <a, G> + <b, H> + blinding * B_blinding
+    /// ```
+    ///
+    /// `a` and `b` may be shorter than `G`/`H`; only the corresponding
+    /// prefix of generators is used. This generalizes
+    /// [`PedersenGenerators::commit`] from a single value to a vector of
+    /// values, for building range-proof-style gadgets on top of this
+    /// generator set.
+    ///
+    /// # Panics
+    /// Panics if `a` or `b` is longer than `G` or `H` respectively.
+    pub fn commit_vec(&self, a: &[Scalar], b: &[Scalar], blinding: Scalar) -> RistrettoPoint {
+        assert!(a.len() <= self.G.len());
+        assert!(b.len() <= self.H.len());
+
+        let scalars: Vec<Scalar> = a.iter()
+            .chain(b.iter())
+            .chain(::std::iter::once(&blinding))
+            .cloned()
+            .collect();
+        let points: Vec<RistrettoPoint> = self.G[..a.len()]
+            .iter()
+            .chain(self.H[..b.len()].iter())
+            .chain(::std::iter::once(&self.pedersen_generators.B_blinding))
+            .cloned()
+            .collect();
+        ristretto::multiscalar_mul(&scalars, &points)
+    }
+
+    /// Commits to `value` with a blinding factor derived from `rewind_key`
+    /// and `nonce`; see [`PedersenGenerators::commit_rewindable`]. Lets
+    /// `range_proof`/`multi_range_proof` use rewindable commitments from
+    /// just a `GeneratorsView`.
+    pub fn commit_rewindable(
+        &self,
+        value: Scalar,
+        rewind_key: &[u8],
+        nonce: &[u8],
+    ) -> Result<RistrettoPoint, RewindError> {
+        self.pedersen_generators.commit_rewindable(value, rewind_key, nonce)
+    }
+
+    /// Recovers a value committed with [`GeneratorsView::commit_rewindable`];
+    /// see [`PedersenGenerators::rewind`].
+    pub fn rewind(
+        &self,
+        commitment: RistrettoPoint,
+        rewind_key: &[u8],
+        nonce: &[u8],
+        max_value: u64,
+    ) -> Result<(u64, Scalar), RewindError> {
+        self.pedersen_generators.rewind(commitment, rewind_key, nonce, max_value)
+    }
+}
+
+/// Required length, in bytes, of a rewind key passed to
+/// [`PedersenGenerators::commit_rewindable`] / [`PedersenGenerators::rewind`].
+const REWIND_KEY_LEN: usize = 32;
+
+/// Domain separator absorbed ahead of the rewind key and nonce when
+/// deriving a rewind-mode blinding factor, so that derivation can never
+/// collide with `GeneratorsChain` or transcript hashing that happens to
+/// reuse the same bytes.
+const REWIND_KEY_SEPARATOR: &[u8] = b"Bulletproofs.PedersenGenerators.RewindKeySeparator";
+
+/// Errors produced by [`PedersenGenerators::commit_rewindable`] and
+/// [`PedersenGenerators::rewind`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum RewindError {
+    /// `rewind_key` was not `REWIND_KEY_LEN` bytes long. `REWIND_KEY_SEPARATOR`
+    /// itself is a hardcoded constant, not something callers supply, so a
+    /// wrong-length key is the only way this derivation can fail.
+    InvalidRewindKeySeparator,
+    /// No value in the scanned range reproduced `commitment` once its
+    /// rewind-derived blinding factor was subtracted out; either the
+    /// commitment was not created with this `rewind_key`/`nonce`, or its
+    /// value is outside the scanned range.
+    InvalidCommitmentExtracted,
 }
 
 /// Represents a pair of base points for Pedersen commitments.
@@ -107,6 +326,77 @@ impl PedersenGenerators {
     pub fn commit(&self, value: Scalar, blinding: Scalar) -> RistrettoPoint {
         ristretto::multiscalar_mul(&[value, blinding], &[self.B, self.B_blinding])
     }
+
+    /// Deterministically derives the blinding factor used by
+    /// `commit_rewindable`/`rewind` for a given `rewind_key` and per-proof
+    /// `nonce`, by absorbing the rewind key separator, `rewind_key` and
+    /// `nonce` into a SHAKE256 XOF and reducing one 64-byte block of output
+    /// mod the group order.
+    fn rewind_blinding(rewind_key: &[u8], nonce: &[u8]) -> Result<Scalar, RewindError> {
+        if rewind_key.len() != REWIND_KEY_LEN {
+            return Err(RewindError::InvalidRewindKeySeparator);
+        }
+
+        let mut shake = Keccak::new_shake256();
+        shake.update(REWIND_KEY_SEPARATOR);
+        shake.update(rewind_key);
+        shake.update(nonce);
+        let mut reader = shake.xof();
+        let mut block = [0u8; 64];
+        reader.squeeze(&mut block);
+        Ok(Scalar::from_bytes_mod_order_wide(&block))
+    }
+
+    /// Commits to `value` using a blinding factor derived from
+    /// `rewind_key` and `nonce`, instead of one supplied by the caller.
+    ///
+    /// Anyone later holding `rewind_key` and `nonce` can call
+    /// [`PedersenGenerators::rewind`] to recover `value` from the
+    /// resulting commitment, without the committer storing `value` or its
+    /// blinding factor anywhere — this is the building block for
+    /// wallet-style recovery of committed amounts using only a viewing key.
+    pub fn commit_rewindable(
+        &self,
+        value: Scalar,
+        rewind_key: &[u8],
+        nonce: &[u8],
+    ) -> Result<RistrettoPoint, RewindError> {
+        let blinding = Self::rewind_blinding(rewind_key, nonce)?;
+        Ok(self.commit(value, blinding))
+    }
+
+    /// Recovers the value embedded in `commitment` by
+    /// [`PedersenGenerators::commit_rewindable`], given the same
+    /// `rewind_key` and `nonce` used to create it.
+    ///
+    /// The blinding factor is fully determined by `rewind_key` and
+    /// `nonce`, so subtracting its contribution from `commitment` leaves
+    /// `value * B`. Since range proofs bound `value` to a small range,
+    /// `value` is then recovered by scanning `0..=max_value` for a match.
+    ///
+    /// Returns `RewindError::InvalidCommitmentExtracted` if no value in
+    /// `0..=max_value` reproduces `commitment`.
+    pub fn rewind(
+        &self,
+        commitment: RistrettoPoint,
+        rewind_key: &[u8],
+        nonce: &[u8],
+        max_value: u64,
+    ) -> Result<(u64, Scalar), RewindError> {
+        let blinding = Self::rewind_blinding(rewind_key, nonce)?;
+        let blinding_contribution =
+            ristretto::multiscalar_mul(&[blinding], &[self.B_blinding]);
+        let target = commitment - blinding_contribution;
+
+        let mut candidate = RistrettoPoint::identity();
+        for value in 0..=max_value {
+            if candidate == target {
+                return Ok((value, blinding));
+            }
+            candidate = candidate + self.B;
+        }
+        Err(RewindError::InvalidCommitmentExtracted)
+    }
 }
 
 impl Default for PedersenGenerators {
@@ -121,20 +411,68 @@ impl Default for PedersenGenerators {
 impl Generators {
     /// Creates generators for `m` range proofs of `n` bits each.
     pub fn new(pedersen_generators: PedersenGenerators, n: usize, m: usize) -> Self {
-        let G = GeneratorsChain::new(pedersen_generators.B.compress().as_bytes())
-            .take(n * m)
-            .collect();
-        let H = GeneratorsChain::new(pedersen_generators.B_blinding.compress().as_bytes())
-            .take(n * m)
-            .collect();
-
-        Generators {
+        let mut generators = Generators {
             n,
-            m,
-            pedersen_generators: pedersen_generators,
-            G,
-            H,
+            m: 0,
+            pedersen_generators,
+            G: Vec::new(),
+            H: Vec::new(),
+            precomp: None,
+        };
+        generators.increase_capacity(m);
+        generators
+    }
+
+    /// Builds precomputed multiplication tables for the current `G` and `H`
+    /// generators, so views produced afterwards can use
+    /// [`GeneratorsView::multiscalar_mul_precomputed`]. A one-time cost paid
+    /// to speed up repeated verifications; call it again after
+    /// `increase_capacity`, which invalidates any existing tables.
+    pub fn precompute(&mut self) {
+        let G_tables = self.G.iter().map(|&p| GeneratorsPrecomp::build_table(p)).collect();
+        let H_tables = self.H.iter().map(|&p| GeneratorsPrecomp::build_table(p)).collect();
+        self.precomp = Some(Arc::new(GeneratorsPrecomp { G_tables, H_tables }));
+    }
+
+    /// Grows the generator set to support `new_m` parties instead of `self.m`.
+    ///
+    /// Each party `j` draws its `n` generators from its own independent
+    /// sub-stream of [`GeneratorsChain`], seeded with `label || j`, so
+    /// growing the party count only requires generating the *new*
+    /// parties' `G`/`H` vectors; the ones already computed for parties
+    /// `0..self.m` are left untouched. Does nothing if `new_m <= self.m`.
+    pub fn increase_capacity(&mut self, new_m: usize) {
+        if new_m <= self.m {
+            return;
         }
+
+        for j in self.m..new_m {
+            self.G.extend(
+                GeneratorsChain::new(&Self::party_label(
+                    self.pedersen_generators.B.compress().as_bytes(),
+                    j,
+                )).take(self.n),
+            );
+            self.H.extend(
+                GeneratorsChain::new(&Self::party_label(
+                    self.pedersen_generators.B_blinding.compress().as_bytes(),
+                    j,
+                )).take(self.n),
+            );
+        }
+
+        self.m = new_m;
+        self.precomp = None;
+    }
+
+    /// Builds the `GeneratorsChain` label for party `j`'s sub-stream,
+    /// by appending its index to the chain's base label.
+    fn party_label(base: &[u8], j: usize) -> Vec<u8> {
+        let mut label = base.to_vec();
+        let mut j_bytes = [0u8; 4];
+        LittleEndian::write_u32(&mut j_bytes, j as u32);
+        label.extend_from_slice(&j_bytes);
+        label
     }
 
     /// Returns a view into the entirety of the generators.
@@ -143,6 +481,8 @@ impl Generators {
             pedersen_generators: &self.pedersen_generators,
             G: &self.G[..],
             H: &self.H[..],
+            precomp: self.precomp.as_ref().map(Arc::as_ref),
+            precomp_range: 0..self.G.len(),
         }
     }
 
@@ -155,6 +495,8 @@ impl Generators {
             pedersen_generators: &self.pedersen_generators,
             G: &self.G[lower..upper],
             H: &self.H[lower..upper],
+            precomp: self.precomp.as_ref().map(Arc::as_ref),
+            precomp_range: lower..upper,
         }
     }
 }
@@ -164,6 +506,113 @@ mod tests {
     extern crate hex;
     use super::*;
 
+    #[test]
+    fn increase_capacity_preserves_existing_shares() {
+        let n = 2;
+        let mut gens = Generators::new(PedersenGenerators::default(), n, 2);
+        let share0_before = gens.share(0).G[..].to_vec();
+        let share1_before = gens.share(1).G[..].to_vec();
+
+        gens.increase_capacity(4);
+
+        assert_eq!(gens.m, 4);
+        assert_eq!(share0_before, gens.share(0).G[..].to_vec());
+        assert_eq!(share1_before, gens.share(1).G[..].to_vec());
+
+        // Growing to the same capacity computed directly from scratch
+        // should match the grown generators, since each party's share
+        // only depends on its own index, not on `m`.
+        let direct = Generators::new(PedersenGenerators::default(), n, 4);
+        assert_eq!(
+            direct.share(3).G[..].to_vec(),
+            gens.share(3).G[..].to_vec()
+        );
+    }
+
+    #[test]
+    fn generators_chain_get_matches_iterator() {
+        let chain = GeneratorsChain::new(b"test label");
+        let sequential: Vec<_> = GeneratorsChain::new(b"test label").take(5).collect();
+        for (i, expected) in sequential.iter().enumerate() {
+            assert_eq!(chain.get(i), *expected);
+        }
+    }
+
+    #[test]
+    fn rewind_recovers_value_and_blinding() {
+        let pg = PedersenGenerators::default();
+        let rewind_key = [7u8; 32];
+        let nonce = b"some per-proof nonce";
+        let value = Scalar::from(1234u64);
+
+        let commitment = pg.commit_rewindable(value, &rewind_key, nonce).unwrap();
+        let (recovered_value, recovered_blinding) =
+            pg.rewind(commitment, &rewind_key, nonce, 10_000).unwrap();
+
+        assert_eq!(recovered_value, 1234u64);
+        assert_eq!(commitment, pg.commit(value, recovered_blinding));
+    }
+
+    #[test]
+    fn rewind_rejects_wrong_key_length() {
+        let pg = PedersenGenerators::default();
+        let err = pg.commit_rewindable(Scalar::from(1u64), &[1u8; 16], b"nonce")
+            .unwrap_err();
+        assert_eq!(err, RewindError::InvalidRewindKeySeparator);
+    }
+
+    #[test]
+    fn rewind_rejects_value_out_of_range() {
+        let pg = PedersenGenerators::default();
+        let rewind_key = [9u8; 32];
+        let nonce = b"nonce";
+        let commitment = pg.commit_rewindable(Scalar::from(500u64), &rewind_key, nonce).unwrap();
+
+        let err = pg.rewind(commitment, &rewind_key, nonce, 100).unwrap_err();
+        assert_eq!(err, RewindError::InvalidCommitmentExtracted);
+    }
+
+    #[test]
+    fn precomputed_multiscalar_mul_matches_plain() {
+        let n = 4;
+        let mut gens = Generators::new(PedersenGenerators::default(), n, 1);
+        gens.precompute();
+
+        let a: Vec<Scalar> = (1..=n as u64).map(Scalar::from).collect();
+        let b: Vec<Scalar> = (1..=n as u64).map(|x| Scalar::from(x * 7)).collect();
+
+        let view = gens.share(0);
+        let scalars: Vec<Scalar> = a.iter().chain(b.iter()).cloned().collect();
+        let points: Vec<RistrettoPoint> = view.G.iter().chain(view.H.iter()).cloned().collect();
+        let expected = ristretto::multiscalar_mul(&scalars, &points);
+        assert_eq!(expected, view.multiscalar_mul_precomputed(&a, &b));
+    }
+
+    #[test]
+    fn commit_vec_matches_manual_multiscalar_mul() {
+        let n = 4;
+        let gens = Generators::new(PedersenGenerators::default(), n, 1);
+        let view = gens.share(0);
+
+        let a: Vec<Scalar> = (1..=n as u64).map(Scalar::from).collect();
+        let b: Vec<Scalar> = (1..=n as u64).map(|x| Scalar::from(x * 3)).collect();
+        let blinding = Scalar::from(42u64);
+
+        let scalars: Vec<Scalar> = a.iter()
+            .chain(b.iter())
+            .chain(::std::iter::once(&blinding))
+            .cloned()
+            .collect();
+        let points: Vec<RistrettoPoint> = view.G.iter()
+            .chain(view.H.iter())
+            .chain(::std::iter::once(&gens.all().pedersen_generators.B_blinding))
+            .cloned()
+            .collect();
+        let expected = ristretto::multiscalar_mul(&scalars, &points);
+
+        assert_eq!(expected, view.commit_vec(&a, &b, blinding));
+    }
+
     #[test]
     fn rangeproof_generators() {
         let n = 2;